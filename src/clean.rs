@@ -52,8 +52,14 @@ impl<T: Error + 'static> CleanedErrors for T {
 /// print the whole chain.
 ///
 /// This iterator checks if the error message contains the source
-/// error's message as a suffix and removes it.
-pub struct CleanedErrorText<'a, 'b>(Option<CleanedErrorTextStep<'a, 'b>>);
+/// error's message as a suffix, possibly wrapped in quotes/parens, or
+/// as an internal substring with an adjacent separator, and removes it.
+/// Cleaning can be disabled with [`CleanedErrorText::new_with_options`]
+/// for errors that intentionally repeat text.
+pub struct CleanedErrorText<'a, 'b> {
+    step: Option<CleanedErrorTextStep<'a, 'b>>,
+    clean_enabled: bool,
+}
 
 struct CleanedErrorTextStep<'a, 'b> {
     error: &'a (dyn Error + 'b),
@@ -72,7 +78,16 @@ impl<'a, 'b> CleanedErrorTextStep<'a, 'b> {
 
 impl<'a, 'b> CleanedErrorText<'a, 'b> {
     pub fn new(err: &'a (dyn Error + 'b)) -> Self {
-        Self(Some(CleanedErrorTextStep::new(err)))
+        Self::new_with_options(err, true)
+    }
+
+    /// Construct a new `CleanedErrorText`, optionally disabling de-duplication
+    /// of source text that is repeated in a parent error's message.
+    pub fn new_with_options(err: &'a (dyn Error + 'b), clean_enabled: bool) -> Self {
+        Self {
+            step: Some(CleanedErrorTextStep::new(err)),
+            clean_enabled,
+        }
     }
 }
 
@@ -81,22 +96,20 @@ impl<'a, 'b> Iterator for CleanedErrorText<'a, 'b> {
     type Item = (&'a (dyn Error + 'b), String, bool);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let step = self.0.take()?;
+        let step = self.step.take()?;
         let error_text = step.error_text;
         let err = step.error;
 
         match err.source() {
             Some(source) => {
                 let source_text = source.to_string();
-                let (cleaned_text, cleaned) = error_text
-                    .strip_suffix(&source_text)
-                    .map(|text| {
-                        let text = text.trim_end();
-                        (text.strip_suffix(':').unwrap_or(text).to_owned(), true)
-                    })
-                    .unwrap_or_else(|| (error_text, false));
-
-                self.0 = Some(CleanedErrorTextStep {
+                let (cleaned_text, cleaned) = if self.clean_enabled {
+                    clean(&error_text, &source_text).unwrap_or_else(|| (error_text.clone(), false))
+                } else {
+                    (error_text.clone(), false)
+                };
+
+                self.step = Some(CleanedErrorTextStep {
                     error: source,
                     error_text: source_text,
                 });
@@ -106,3 +119,171 @@ impl<'a, 'b> Iterator for CleanedErrorText<'a, 'b> {
         }
     }
 }
+
+/// Removes `source_text` from `parent_text`, either as a (possibly wrapped)
+/// suffix or as an internal substring with an adjacent separator.
+///
+/// Returns `None` if `source_text` isn't found, or if removing it would
+/// leave the parent message empty, in which case the original text should
+/// be kept as-is.
+fn clean(parent_text: &str, source_text: &str) -> Option<(String, bool)> {
+    if source_text.is_empty() {
+        return None;
+    }
+
+    if let Some(cleaned) = clean_suffix(parent_text, source_text) {
+        return Some((cleaned, true));
+    }
+
+    clean_internal(parent_text, source_text).map(|cleaned| (cleaned, true))
+}
+
+/// Strips `source_text` from the end of `parent_text`, allowing it to be
+/// wrapped in a single pair of quotes or parens, and trims the trailing
+/// whitespace and at most one `:` or `.` left behind.
+fn clean_suffix(parent_text: &str, source_text: &str) -> Option<String> {
+    let rest = parent_text.strip_suffix(source_text).or_else(|| {
+        [('"', '"'), ('\'', '\''), ('(', ')')]
+            .into_iter()
+            .find_map(|(open, close)| {
+                let wrapped = format!("{open}{source_text}{close}");
+                parent_text.strip_suffix(wrapped.as_str())
+            })
+    })?;
+
+    let rest = rest.trim_end();
+    let rest = rest
+        .strip_suffix(':')
+        .or_else(|| rest.strip_suffix('.'))
+        .unwrap_or(rest)
+        .trim_end();
+
+    if rest.is_empty() {
+        None
+    } else {
+        Some(rest.to_owned())
+    }
+}
+
+/// Returns true for characters that can separate a repeated source message
+/// from the rest of the parent message.
+fn is_separator(c: char) -> bool {
+    matches!(c, ':' | ',' | '.' | '(' | ')') || c.is_whitespace()
+}
+
+/// Finds `source_text` occurring inside `parent_text` (not just as a
+/// suffix), with a separator immediately before or after it, and collapses
+/// the duplicated span, the separator on each side of it where there is
+/// one, and the whitespace next to them down to a single space.
+///
+/// Returns `None` unless there is non-empty text left on both sides, so
+/// that a message which merely starts or ends with its source text isn't
+/// hollowed out.
+fn clean_internal(parent_text: &str, source_text: &str) -> Option<String> {
+    let idx = parent_text.find(source_text)?;
+    let end = idx + source_text.len();
+    let before = &parent_text[..idx];
+    let after = &parent_text[end..];
+
+    let before_sep_len = before
+        .chars()
+        .next_back()
+        .filter(|c| is_separator(*c))
+        .map(char::len_utf8);
+    let after_sep_len = after
+        .chars()
+        .next()
+        .filter(|c| is_separator(*c))
+        .map(char::len_utf8);
+    if before_sep_len.is_none() && after_sep_len.is_none() {
+        return None;
+    }
+
+    let before = match before_sep_len {
+        Some(len) => &before[..before.len() - len],
+        None => before,
+    };
+    let after = match after_sep_len {
+        Some(len) => &after[len..],
+        None => after,
+    };
+
+    let before = before.trim_end();
+    let after = after.trim_start();
+    if before.is_empty() || after.is_empty() {
+        return None;
+    }
+
+    Some(format!("{before} {after}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suffix_exact() {
+        assert_eq!(
+            clean_suffix("fn failed: oh no!", "oh no!"),
+            Some("fn failed".to_owned())
+        );
+    }
+
+    #[test]
+    fn suffix_wrapped_in_quotes() {
+        assert_eq!(
+            clean_suffix("opening file: \"oh no!\"", "oh no!"),
+            Some("opening file".to_owned())
+        );
+    }
+
+    #[test]
+    fn suffix_wrapped_in_parens() {
+        assert_eq!(
+            clean_suffix("request failed (timed out)", "timed out"),
+            Some("request failed".to_owned())
+        );
+    }
+
+    #[test]
+    fn suffix_would_empty_parent_is_skipped() {
+        assert_eq!(clean_suffix("oh no!", "oh no!"), None);
+    }
+
+    #[test]
+    fn internal_substring_with_separator_before_is_collapsed() {
+        assert_eq!(
+            clean_internal("failed: oh no! while doing work", "oh no!"),
+            Some("failed: while doing work".to_owned())
+        );
+    }
+
+    #[test]
+    fn internal_substring_with_separator_after_is_collapsed() {
+        assert_eq!(
+            clean_internal("contextoh no!: more", "oh no!"),
+            Some("context more".to_owned())
+        );
+    }
+
+    #[test]
+    fn internal_substring_without_separator_is_untouched() {
+        assert_eq!(clean_internal("ohno! happened", "ohno"), None);
+    }
+
+    #[test]
+    fn internal_substring_with_multibyte_separator_before_does_not_panic() {
+        assert_eq!(
+            clean_internal("failed:\u{A0}oh no! while doing work", "oh no!"),
+            Some("failed: while doing work".to_owned())
+        );
+    }
+
+    #[test]
+    fn internal_substring_with_separators_on_both_sides_strips_both() {
+        assert_eq!(
+            clean_internal("context: oh no!: more", "oh no!"),
+            Some("context: more".to_owned())
+        );
+    }
+}
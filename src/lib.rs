@@ -1,12 +1,24 @@
+#![cfg_attr(
+    feature = "unstable-provider-api",
+    feature(error_generic_member_access)
+)]
+
 mod clean;
 
 pub use clean::{CleanedErrorText, CleanedErrors};
 
 use core::fmt;
+#[cfg(feature = "unstable-provider-api")]
+use std::backtrace::Backtrace;
 use std::error::Error;
 
 /// Provides the `report` method for `std::error::Error`,
 /// converting the error to a `Report`.
+///
+/// With the `unstable-provider-api` feature, this additionally requires
+/// `Self: 'static` so that `Report` can look up a backtrace through the
+/// error provider API.
+#[cfg(not(feature = "unstable-provider-api"))]
 pub trait Reportable {
     fn report(self) -> Report<Self>
     where
@@ -14,6 +26,7 @@ pub trait Reportable {
         Self: std::marker::Sized;
 }
 
+#[cfg(not(feature = "unstable-provider-api"))]
 impl<E: Error> Reportable for E {
     fn report(self) -> Report<Self>
     where
@@ -24,19 +37,59 @@ impl<E: Error> Reportable for E {
     }
 }
 
+#[cfg(feature = "unstable-provider-api")]
+pub trait Reportable {
+    fn report(self) -> Report<Self>
+    where
+        Self: std::error::Error,
+        Self: std::marker::Sized,
+        Self: 'static;
+}
+
+#[cfg(feature = "unstable-provider-api")]
+impl<E: Error + 'static> Reportable for E {
+    fn report(self) -> Report<Self>
+    where
+        Self: std::error::Error,
+        Self: std::marker::Sized,
+        Self: 'static,
+    {
+        Report::new(self)
+    }
+}
+
 /// AsRefError is needed because `anyhow::Error` only implements `AsRef<dyn Error>`, not `Error`,
 /// but `&dyn Error` does not implement `AsRef<dyn Error>` because `AsRef` doesn't have a blanket
 /// implementation (https://doc.rust-lang.org/std/convert/trait.AsRef.html#reflexivity).
+///
+/// With the `unstable-provider-api` feature, `as_ref_error` returns a
+/// `'static`-bounded trait object so `Report` can look up a backtrace
+/// through the error provider API; this narrows the blanket impl to
+/// `'static` errors only when that feature is enabled.
+#[cfg(not(feature = "unstable-provider-api"))]
 pub trait AsRefError {
     fn as_ref_error(&self) -> &dyn Error;
 }
 
+#[cfg(not(feature = "unstable-provider-api"))]
 impl<E: Error> AsRefError for E {
     fn as_ref_error(&self) -> &dyn Error {
         self
     }
 }
 
+#[cfg(feature = "unstable-provider-api")]
+pub trait AsRefError {
+    fn as_ref_error(&self) -> &(dyn Error + 'static);
+}
+
+#[cfg(feature = "unstable-provider-api")]
+impl<E: Error + 'static> AsRefError for E {
+    fn as_ref_error(&self) -> &(dyn Error + 'static) {
+        self
+    }
+}
+
 // This implementation is unfortunately not possible.
 /*
 impl<E: AsRef<dyn Error>> AsRefError for E {
@@ -55,6 +108,21 @@ impl<E: AsRef<dyn Error>> AsRefError for E {
 /// implementation prints all errors on one line separated by a colon.
 /// Using alternate formatting (`{:#}`) is identical to the debug implementation.
 ///
+/// With the `unstable-provider-api` feature (which requires nightly, since it
+/// depends on the unstable `error_generic_member_access` feature), the
+/// multiline output also appends a `Backtrace` section if any error in the
+/// chain provides one.
+///
+/// The choice between multiline and single-line output, whether source
+/// message de-duplication is applied, and whether a backtrace is shown, can
+/// also be set explicitly with the [`Report::pretty`], [`Report::clean`] and
+/// [`Report::show_backtrace`] builder methods, overriding the
+/// `Debug`/`Display`-based default.
+///
+/// [`Report::chain`] exposes the same cleaned error chain programmatically,
+/// for callers that want to build their own renderer instead of using
+/// `Debug`/`Display`.
+///
 /// The debug implementation is intended for cases where errors are debug printed,
 /// for example returning an error from main or using `expect` on `Result`:
 ///
@@ -75,11 +143,23 @@ impl<E: AsRef<dyn Error>> AsRefError for E {
 /// # use error_report::Report;
 /// let i: i8 = 256.try_into().map_err(Report::from).expect("conversion error");
 /// ```
-pub struct Report<E: AsRefError>(E);
+pub struct Report<E: AsRefError> {
+    error: E,
+    pretty: Option<bool>,
+    clean: Option<bool>,
+    #[cfg(feature = "unstable-provider-api")]
+    show_backtrace: Option<bool>,
+}
 
 impl<E: AsRefError> From<E> for Report<E> {
     fn from(value: E) -> Self {
-        Self(value)
+        Self {
+            error: value,
+            pretty: None,
+            clean: None,
+            #[cfg(feature = "unstable-provider-api")]
+            show_backtrace: None,
+        }
     }
 }
 
@@ -89,10 +169,53 @@ impl<E: AsRefError> Report<E> {
         Self::from(err)
     }
 
-    fn format(&self, f: &mut fmt::Formatter<'_>, multiline: bool) -> fmt::Result {
-        let cleaned_texts = CleanedErrorText::new(self.0.as_ref_error())
+    /// Set whether the report should be rendered multiline (`true`) or on a
+    /// single line (`false`).
+    ///
+    /// When unset, this falls back to the current behavior of rendering
+    /// multiline for `Debug` and according to `f.alternate()` for `Display`.
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = Some(pretty);
+        self
+    }
+
+    /// Set whether source messages that are repeated in a parent error's
+    /// message are cleaned up (the default), or left untouched.
+    ///
+    /// Disable this for errors whose messages intentionally repeat text
+    /// from their source.
+    pub fn clean(mut self, clean: bool) -> Self {
+        self.clean = Some(clean);
+        self
+    }
+
+    /// Set whether a backtrace, if one is available anywhere in the error
+    /// chain, should be appended to multiline output.
+    ///
+    /// When unset, a backtrace is shown whenever one is available. Requires
+    /// the `unstable-provider-api` feature.
+    #[cfg(feature = "unstable-provider-api")]
+    pub fn show_backtrace(mut self, show_backtrace: bool) -> Self {
+        self.show_backtrace = Some(show_backtrace);
+        self
+    }
+
+    /// Returns an iterator over the cleaned error chain, yielding each
+    /// error together with its cleaned display text and whether that text
+    /// was changed by cleaning.
+    ///
+    /// This gives programmatic access to the same data the `Debug` and
+    /// `Display` implementations render, for callers that want to build
+    /// their own output, e.g. structured logging.
+    pub fn chain(&self) -> impl Iterator<Item = (&dyn Error, String, bool)> {
+        CleanedErrorText::new_with_options(self.error.as_ref_error(), self.clean.unwrap_or(true))
             .filter(|(_, t, _)| !t.is_empty())
-            .enumerate();
+    }
+
+    fn format(&self, f: &mut fmt::Formatter<'_>, multiline: bool) -> fmt::Result {
+        let multiline = self.pretty.unwrap_or(multiline);
+
+        let cleaned_texts = self.chain().enumerate();
 
         if !multiline {
             for (i, (_, text, _)) in cleaned_texts {
@@ -112,12 +235,35 @@ impl<E: AsRefError> Report<E> {
                     writeln!(f, "    {i}. {text}")?;
                 }
             }
+
+            #[cfg(feature = "unstable-provider-api")]
+            if self.show_backtrace.unwrap_or(true) {
+                if let Some(bt) = find_backtrace(self.error.as_ref_error()) {
+                    write!(f, "\nBacktrace:\n{bt}")?;
+                }
+            }
         }
 
         Ok(())
     }
 }
 
+/// Walks the source chain looking for the first error that provides a
+/// `Backtrace` through the `Error` provider API.
+///
+/// Context wrappers such as `anyhow` attach the backtrace to the innermost
+/// error they wrap rather than to themselves, so the outermost error in the
+/// chain is usually not the one that actually carries it.
+#[cfg(feature = "unstable-provider-api")]
+fn find_backtrace<'a>(mut err: &'a (dyn Error + 'static)) -> Option<&'a Backtrace> {
+    loop {
+        if let Some(bt) = core::error::request_ref::<Backtrace>(err) {
+            return Some(bt);
+        }
+        err = err.source()?;
+    }
+}
+
 impl<E: AsRefError> fmt::Debug for Report<E> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.format(f, true)
@@ -134,12 +280,20 @@ impl<E: AsRefError> fmt::Display for Report<E> {
 /// to be used with `Report` without having to implement `AsRefError` on it.
 pub struct Ref<E>(E);
 
+#[cfg(not(feature = "unstable-provider-api"))]
 impl<E: AsRef<dyn Error>> AsRefError for Ref<E> {
     fn as_ref_error(&self) -> &dyn Error {
         self.0.as_ref()
     }
 }
 
+#[cfg(feature = "unstable-provider-api")]
+impl<E: AsRef<dyn Error>> AsRefError for Ref<E> {
+    fn as_ref_error(&self) -> &(dyn Error + 'static) {
+        self.0.as_ref()
+    }
+}
+
 impl<E: AsRef<dyn Error>> Report<Ref<E>> {
     /// Construct a new `Report` from a type that implements `AsRef<dyn Error>`.
     pub fn from_ref(value: E) -> Self {
@@ -198,8 +352,11 @@ mod tests {
         let debug_string = format!("{:?}", err);
 
         assert_eq!(normal_string, "fn failed: oh no!");
-        assert_eq!(alt_string, "fn failed\n\nCaused by:\n    1. oh no!\n");
-        assert_eq!(debug_string, "fn failed\n\nCaused by:\n    1. oh no!\n");
+        // `anyhow` may attach a backtrace to its error depending on the
+        // environment, which the unstable-provider-api feature would then
+        // append after this prefix.
+        assert!(alt_string.starts_with("fn failed\n\nCaused by:\n    1. oh no!\n"));
+        assert!(debug_string.starts_with("fn failed\n\nCaused by:\n    1. oh no!\n"));
 
         let err = custom_caller().expect_err("function did not return error");
 
@@ -212,7 +369,10 @@ mod tests {
         assert_eq!(debug_string, "custom error");
 
         let err = custom_fn().expect_err("function did not return error");
+        #[cfg(not(feature = "unstable-provider-api"))]
         let report = Report::from(&err);
+        #[cfg(feature = "unstable-provider-api")]
+        let report = Report::from(err);
         let normal_string = format!("{}", report);
         let alt_string = format!("{:#}", report);
         let debug_string = format!("{:?}", report);
@@ -220,6 +380,98 @@ mod tests {
         assert_eq!(normal_string, "custom error");
         assert_eq!(alt_string, "custom error");
         assert_eq!(debug_string, "custom error");
-        _ = err
+        #[cfg(not(feature = "unstable-provider-api"))]
+        {
+            _ = err;
+        }
+    }
+
+    /// Builds a `Report` over a two-level anyhow chain ("fn failed" <- "oh
+    /// no!"), for tests that exercise the builder methods.
+    fn anyhow_chain() -> Report<impl AsRefError> {
+        fn anyhow_fn() -> anyhow::Result<()> {
+            Err(anyhow::anyhow!("oh no!"))
+        }
+
+        fn anyhow_caller() -> Result<(), Report<impl AsRefError>> {
+            anyhow_fn().context("fn failed")?;
+
+            Ok(())
+        }
+
+        anyhow_caller().expect_err("function did not return error")
+    }
+
+    #[derive(Debug)]
+    struct InnerError;
+
+    impl std::fmt::Display for InnerError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "oh no!")
+        }
+    }
+
+    impl std::error::Error for InnerError {}
+
+    #[derive(Debug)]
+    struct OuterError(InnerError);
+
+    impl std::fmt::Display for OuterError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "failed: oh no!")
+        }
+    }
+
+    impl std::error::Error for OuterError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    /// Builds a `Report` over a two-level chain whose outer message
+    /// ("failed: oh no!") repeats its source's message ("oh no!") verbatim,
+    /// for tests that exercise cleaning.
+    fn repeated_message_chain() -> Report<OuterError> {
+        Report::new(OuterError(InnerError))
+    }
+
+    #[test]
+    fn test_pretty() {
+        let multiline = format!("{}", anyhow_chain().pretty(true));
+        // `anyhow` may attach a backtrace to its error depending on the
+        // environment, which the unstable-provider-api feature would then
+        // append after this prefix.
+        assert!(multiline.starts_with("fn failed\n\nCaused by:\n    1. oh no!\n"));
+
+        let single_line = format!("{:?}", anyhow_chain().pretty(false));
+        assert_eq!(single_line, "fn failed: oh no!");
+    }
+
+    #[cfg(feature = "unstable-provider-api")]
+    #[test]
+    fn test_show_backtrace() {
+        let hidden = format!("{:?}", anyhow_chain().show_backtrace(false));
+        assert!(!hidden.contains("\nBacktrace:\n"));
+    }
+
+    #[test]
+    fn test_clean() {
+        let cleaned = format!("{:?}", repeated_message_chain().clean(true));
+        assert!(cleaned.starts_with("failed\n\nCaused by:\n    1. oh no!\n"));
+
+        let uncleaned = format!("{:?}", repeated_message_chain().clean(false));
+        assert!(uncleaned.starts_with("failed: oh no!\n\nCaused by:\n    1. oh no!\n"));
+    }
+
+    #[test]
+    fn test_chain() {
+        let texts: Vec<_> = repeated_message_chain()
+            .chain()
+            .map(|(_, text, cleaned)| (text, cleaned))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![("failed".to_owned(), true), ("oh no!".to_owned(), false)]
+        );
     }
 }